@@ -0,0 +1,340 @@
+use std::{
+    path::Path,
+    sync::atomic::{AtomicBool, AtomicI32, Ordering},
+    thread::sleep,
+    time::Duration,
+};
+
+use anyhow::anyhow;
+
+pub type Pid = libc::pid_t;
+pub const SIGTERM: libc::c_int = libc::SIGTERM;
+pub const SIGKILL: libc::c_int = libc::SIGKILL;
+
+/// Resolves a signal name (`SIGTERM`, `TERM`, case-insensitive) or a raw
+/// number to the `SIG*` constant `kill`/[`send_signal`] expects.
+pub fn signal_from_name(name: &str) -> anyhow::Result<libc::c_int> {
+    if let Ok(n) = name.parse::<libc::c_int>() {
+        return Ok(n);
+    }
+
+    let upper = name.to_ascii_uppercase();
+    Ok(match upper.strip_prefix("SIG").unwrap_or(&upper) {
+        "HUP" => libc::SIGHUP,
+        "INT" => libc::SIGINT,
+        "QUIT" => libc::SIGQUIT,
+        "KILL" => libc::SIGKILL,
+        "USR1" => libc::SIGUSR1,
+        "USR2" => libc::SIGUSR2,
+        "TERM" => libc::SIGTERM,
+        "CONT" => libc::SIGCONT,
+        "STOP" => libc::SIGSTOP,
+        other => return Err(anyhow!("Unknown signal `{}`", other)),
+    })
+}
+
+/// Outcome of a `fork(2)` call: the two execution paths a caller ends up in
+/// after the syscall returns.
+#[derive(Debug)]
+pub enum Fork {
+    Parent(Pid),
+    Child,
+}
+
+/// Detaches the current process from its controlling terminal and forks
+/// into the background, the way a classic unix daemon does.
+pub fn daemon() -> anyhow::Result<Fork> {
+    match unsafe { libc::fork() } {
+        -1 => Err(anyhow!("fork failed: {}", std::io::Error::last_os_error())),
+        0 => {
+            if unsafe { libc::setsid() } == -1 {
+                return Err(anyhow!("setsid failed: {}", std::io::Error::last_os_error()));
+            }
+            Ok(Fork::Child)
+        }
+        pid => Ok(Fork::Parent(pid)),
+    }
+}
+
+/// Forks the current process without detaching it, for spawning a
+/// supervised grandchild from an already-daemonized process.
+pub fn fork() -> anyhow::Result<Fork> {
+    match unsafe { libc::fork() } {
+        -1 => Err(anyhow!("fork failed: {}", std::io::Error::last_os_error())),
+        0 => Ok(Fork::Child),
+        pid => Ok(Fork::Parent(pid)),
+    }
+}
+
+fn decode_exit_status(status: libc::c_int) -> i32 {
+    if libc::WIFEXITED(status) {
+        libc::WEXITSTATUS(status)
+    } else if libc::WIFSIGNALED(status) {
+        -libc::WTERMSIG(status)
+    } else {
+        status
+    }
+}
+
+/// Blocks until `pid` exits, returning its exit code, or the signal that
+/// killed it encoded as a negative number.
+pub fn waitpid(pid: Pid) -> anyhow::Result<i32> {
+    let mut status: libc::c_int = 0;
+    if unsafe { libc::waitpid(pid, &mut status, 0) } == -1 {
+        return Err(anyhow!("waitpid failed: {}", std::io::Error::last_os_error()));
+    }
+
+    Ok(decode_exit_status(status))
+}
+
+/// Non-blocking variant of [`waitpid`]: returns `None` while `pid` is still
+/// running instead of blocking for it to exit.
+pub fn try_waitpid(pid: Pid) -> anyhow::Result<Option<i32>> {
+    let mut status: libc::c_int = 0;
+    match unsafe { libc::waitpid(pid, &mut status, libc::WNOHANG) } {
+        0 => Ok(None),
+        -1 => Err(anyhow!("waitpid failed: {}", std::io::Error::last_os_error())),
+        _ => Ok(Some(decode_exit_status(status))),
+    }
+}
+
+pub fn is_process_running(pid: i32) -> bool {
+    unsafe { libc::kill(pid, 0) == 0 }
+}
+
+pub fn send_signal(pid: i32, signal: libc::c_int) -> anyhow::Result<()> {
+    if unsafe { libc::kill(pid, signal) } == -1 {
+        return Err(anyhow!("Failed to signal {}: {}", pid, std::io::Error::last_os_error()));
+    }
+    Ok(())
+}
+
+/// Signals a configured/overridden stop signal is allowed to resolve to.
+/// `SIGKILL`/`SIGSTOP` can't be caught, so sending either straight to the
+/// supervisor kills or freezes it before it gets a chance to act - see
+/// [`is_catchable_signal`].
+pub const CATCHABLE_SIGNALS: &[libc::c_int] =
+    &[libc::SIGTERM, libc::SIGINT, libc::SIGHUP, libc::SIGQUIT, libc::SIGUSR1, libc::SIGUSR2];
+
+pub fn is_catchable_signal(signal: libc::c_int) -> bool {
+    CATCHABLE_SIGNALS.contains(&signal)
+}
+
+static SHUTDOWN_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn handle_shutdown_signal(_: libc::c_int) {
+    SHUTDOWN_REQUESTED.store(true, Ordering::SeqCst);
+}
+
+/// Installs a handler for every [`CATCHABLE_SIGNALS`] entry, folding
+/// whichever one arrives into [`shutdown_requested`].
+pub fn install_shutdown_handler() {
+    for signal in CATCHABLE_SIGNALS {
+        unsafe {
+            libc::signal(*signal, handle_shutdown_signal as *const () as libc::sighandler_t);
+        }
+    }
+}
+
+pub fn shutdown_requested() -> bool {
+    SHUTDOWN_REQUESTED.load(Ordering::SeqCst)
+}
+
+/// Resolves an rlimit name from `.worker.toml` (`nofile`, `nproc`, `as`,
+/// `cpu`) to the `RLIMIT_*` constant `setrlimit` expects.
+pub fn rlimit_resource_from_name(name: &str) -> anyhow::Result<libc::__rlimit_resource_t> {
+    Ok(match name {
+        "nofile" => libc::RLIMIT_NOFILE,
+        "nproc" => libc::RLIMIT_NPROC,
+        "as" => libc::RLIMIT_AS,
+        "cpu" => libc::RLIMIT_CPU,
+        other => return Err(anyhow!("Unknown rlimit `{}`", other)),
+    })
+}
+
+/// Applies a soft/hard `setrlimit` to the calling process, to be called in
+/// the child right before `exec`.
+pub fn set_rlimit(resource: libc::__rlimit_resource_t, soft: u64, hard: u64) -> anyhow::Result<()> {
+    let limit = libc::rlimit { rlim_cur: soft as libc::rlim_t, rlim_max: hard as libc::rlim_t };
+    if unsafe { libc::setrlimit(resource, &limit) } == -1 {
+        return Err(anyhow!("setrlimit failed: {}", std::io::Error::last_os_error()));
+    }
+    Ok(())
+}
+
+/// Which Linux namespaces to `unshare` into before `exec`. `pid` isn't
+/// among these - see [`fork_into_pid_namespace`] for why it needs its own
+/// entry point instead of just another flag here.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NamespaceToggles {
+    pub net: bool,
+    pub mount: bool,
+    pub uts: bool,
+}
+
+/// Unshares the requested namespaces from the calling process. A kernel
+/// lacking a namespace, or a caller lacking the privilege to create one,
+/// surfaces as an error here rather than silently leaving the process
+/// unconfined.
+pub fn unshare_namespaces(toggles: NamespaceToggles) -> anyhow::Result<()> {
+    let mut flags = 0;
+    if toggles.net {
+        flags |= libc::CLONE_NEWNET;
+    }
+    if toggles.mount {
+        flags |= libc::CLONE_NEWNS;
+    }
+    if toggles.uts {
+        flags |= libc::CLONE_NEWUTS;
+    }
+
+    if flags == 0 {
+        return Ok(());
+    }
+
+    if unsafe { libc::unshare(flags) } == -1 {
+        return Err(anyhow!(
+            "unshare failed (kernel support or privilege missing): {}",
+            std::io::Error::last_os_error()
+        ));
+    }
+    Ok(())
+}
+
+static RELAY_SIGNAL: AtomicI32 = AtomicI32::new(0);
+
+extern "C" fn record_relay_signal(sig: libc::c_int) {
+    RELAY_SIGNAL.store(sig, Ordering::SeqCst);
+}
+
+// Catches the same signals `install_shutdown_handler` does, recording
+// whichever arrives instead of acting on it, so the PID-namespace relay
+// loop below can forward it to the real child.
+fn install_relay_signal_handler() {
+    for sig in CATCHABLE_SIGNALS {
+        unsafe {
+            libc::signal(*sig, record_relay_signal as *const () as libc::sighandler_t);
+        }
+    }
+}
+
+fn take_relay_signal() -> Option<libc::c_int> {
+    match RELAY_SIGNAL.swap(0, Ordering::SeqCst) {
+        0 => None,
+        sig => Some(sig),
+    }
+}
+
+/// `unshare(CLONE_NEWPID)` only affects future children, so this forks once
+/// more and returns `Ok(())` only to the grandchild, which lands as PID 1
+/// of the new namespace. The parent never returns: it relays signals to
+/// the grandchild and mirrors its exit status. Writes the grandchild's
+/// real pid to `pid_file` so `force_kill_target` (main.rs) can reach it
+/// directly for signals that can't be relayed.
+pub fn fork_into_pid_namespace(pid_file: &Path) -> anyhow::Result<()> {
+    if unsafe { libc::unshare(libc::CLONE_NEWPID) } == -1 {
+        return Err(anyhow!(
+            "unshare(CLONE_NEWPID) failed (kernel support or privilege missing): {}",
+            std::io::Error::last_os_error()
+        ));
+    }
+
+    match fork()? {
+        Fork::Child => Ok(()),
+        Fork::Parent(pid) => {
+            let _ = std::fs::write(pid_file, pid.to_string());
+            install_relay_signal_handler();
+
+            loop {
+                if let Some(sig) = take_relay_signal() {
+                    let _ = send_signal(pid, sig);
+                }
+
+                if let Some(status) = try_waitpid(pid)? {
+                    std::process::exit(if status >= 0 { status } else { 128 + (-status) });
+                }
+
+                sleep(Duration::from_millis(50));
+            }
+        }
+    }
+}
+
+// libc doesn't expose these: they're the fixed capability numbers from
+// <linux/capability.h>, used as-is by `prctl(PR_CAPBSET_DROP, ..)`.
+const CAP_CHOWN: libc::c_int = 0;
+const CAP_DAC_OVERRIDE: libc::c_int = 1;
+const CAP_KILL: libc::c_int = 5;
+const CAP_SETGID: libc::c_int = 6;
+const CAP_SETUID: libc::c_int = 7;
+const CAP_NET_BIND_SERVICE: libc::c_int = 10;
+const CAP_NET_ADMIN: libc::c_int = 12;
+const CAP_NET_RAW: libc::c_int = 13;
+const CAP_SYS_MODULE: libc::c_int = 16;
+const CAP_SYS_CHROOT: libc::c_int = 18;
+const CAP_SYS_PTRACE: libc::c_int = 19;
+const CAP_SYS_ADMIN: libc::c_int = 21;
+const CAP_SYS_TIME: libc::c_int = 25;
+
+/// Resolves a `CAP_*` name to the numeric value `prctl(PR_CAPBSET_DROP, ..)`
+/// expects.
+pub fn capability_from_name(name: &str) -> anyhow::Result<libc::c_int> {
+    Ok(match name.to_ascii_uppercase().as_str() {
+        "CAP_CHOWN" => CAP_CHOWN,
+        "CAP_DAC_OVERRIDE" => CAP_DAC_OVERRIDE,
+        "CAP_KILL" => CAP_KILL,
+        "CAP_NET_ADMIN" => CAP_NET_ADMIN,
+        "CAP_NET_BIND_SERVICE" => CAP_NET_BIND_SERVICE,
+        "CAP_NET_RAW" => CAP_NET_RAW,
+        "CAP_SETUID" => CAP_SETUID,
+        "CAP_SETGID" => CAP_SETGID,
+        "CAP_SYS_ADMIN" => CAP_SYS_ADMIN,
+        "CAP_SYS_CHROOT" => CAP_SYS_CHROOT,
+        "CAP_SYS_MODULE" => CAP_SYS_MODULE,
+        "CAP_SYS_PTRACE" => CAP_SYS_PTRACE,
+        "CAP_SYS_TIME" => CAP_SYS_TIME,
+        other => return Err(anyhow!("Unknown capability `{}`", other)),
+    })
+}
+
+/// Clears `cap` from the calling process's capability bounding set.
+pub fn drop_capability(cap: libc::c_int) -> anyhow::Result<()> {
+    if unsafe { libc::prctl(libc::PR_CAPBSET_DROP, cap, 0, 0, 0) } == -1 {
+        return Err(anyhow!("prctl(PR_CAPBSET_DROP) failed: {}", std::io::Error::last_os_error()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signal_from_name_accepts_sig_prefixed_and_bare_names() {
+        assert_eq!(signal_from_name("SIGTERM").unwrap(), libc::SIGTERM);
+        assert_eq!(signal_from_name("TERM").unwrap(), libc::SIGTERM);
+    }
+
+    #[test]
+    fn signal_from_name_is_case_insensitive() {
+        assert_eq!(signal_from_name("sigterm").unwrap(), libc::SIGTERM);
+        assert_eq!(signal_from_name("term").unwrap(), libc::SIGTERM);
+    }
+
+    #[test]
+    fn signal_from_name_accepts_raw_numbers() {
+        assert_eq!(signal_from_name("9").unwrap(), 9);
+    }
+
+    #[test]
+    fn signal_from_name_rejects_unknown_names() {
+        assert!(signal_from_name("NOTASIGNAL").is_err());
+    }
+
+    #[test]
+    fn is_catchable_signal_rejects_kill_and_stop() {
+        assert!(!is_catchable_signal(libc::SIGKILL));
+        assert!(!is_catchable_signal(libc::SIGSTOP));
+        assert!(is_catchable_signal(libc::SIGTERM));
+    }
+}
@@ -1,22 +1,31 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{HashMap, HashSet, VecDeque},
     fs::File,
-    io::{BufRead, BufReader, Read},
+    io::{BufRead, BufReader, Read, Seek, SeekFrom},
     os::{
         fd::{FromRawFd, IntoRawFd},
-        unix::process::CommandExt,
+        unix::{fs::MetadataExt, process::CommandExt},
     },
     path::{Path, PathBuf},
     process::Stdio,
     str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
     thread::sleep,
     time::{Duration, Instant},
 };
 
 use anyhow::{anyhow, bail, Context};
-use clap::{command, Parser};
+use clap::Parser;
 use lazy_static::lazy_static;
-use libc::{daemon, is_process_running, terminate, Fork};
+use libc::{
+    capability_from_name, daemon, drop_capability, fork, fork_into_pid_namespace, install_shutdown_handler,
+    is_catchable_signal, is_process_running, rlimit_resource_from_name, send_signal, set_rlimit, shutdown_requested,
+    signal_from_name, try_waitpid, unshare_namespaces, waitpid, Fork, NamespaceToggles,
+};
+use notify::{RecursiveMode, Watcher};
 use serde::{Deserialize, Serialize};
 
 pub mod libc;
@@ -31,37 +40,187 @@ lazy_static! {
         .expect("Couldn't find config dir");
 }
 
-// TODO: Should not read the entire file. Should only read last x lines or something
-fn log(log_args: LogsArgs) -> Result<(), anyhow::Error> {
-    let log_file = LOG_DIR.join(log_args.project.name);
-    let file = File::open(log_file)?;
+// Seeks backwards from EOF in chunks to find the offset to start reading
+// from so only the last `lines` lines get printed, without reading the
+// whole file.
+fn tail_offset(file: &mut File, lines: usize) -> Result<u64, anyhow::Error> {
+    const CHUNK_SIZE: u64 = 8 * 1024;
+
+    let file_len = file.metadata()?.len();
+    if lines == 0 || file_len == 0 {
+        return Ok(file_len);
+    }
+
+    // A trailing newline terminates the last line rather than starting a new
+    // (empty) one, so it shouldn't count towards `lines`.
+    file.seek(SeekFrom::End(-1))?;
+    let mut last_byte = [0u8];
+    file.read_exact(&mut last_byte)?;
+    let skip_trailing_newline = last_byte[0] == b'\n';
+
+    let mut pos = file_len;
+    let mut found = 0usize;
+    let mut buf = vec![0u8; CHUNK_SIZE as usize];
+
+    while pos > 0 {
+        let chunk_len = CHUNK_SIZE.min(pos);
+        pos -= chunk_len;
+        file.seek(SeekFrom::Start(pos))?;
+        file.read_exact(&mut buf[..chunk_len as usize])?;
+
+        for (i, &byte) in buf[..chunk_len as usize].iter().enumerate().rev() {
+            if byte != b'\n' {
+                continue;
+            }
+
+            let absolute = pos + i as u64;
+            if skip_trailing_newline && absolute == file_len - 1 {
+                continue;
+            }
+
+            found += 1;
+            if found == lines {
+                return Ok(absolute + 1);
+            }
+        }
+    }
+
+    Ok(0)
+}
+
+// Cycled per project so a project's two streams share a color in the
+// interleaved `-f` view.
+const LABEL_COLORS: [u8; 6] = [31, 32, 33, 34, 35, 36];
+
+// One `name.out`/`name.err` file selected by a `logs` invocation.
+struct LogSource {
+    label: String,
+    color: u8,
+    path: PathBuf,
+}
+
+fn log_sources(log_args: &LogsArgs) -> Vec<LogSource> {
+    let streams: &[&str] = match log_args.stream {
+        Stream::Out => &["out"],
+        Stream::Err => &["err"],
+        Stream::Both => &["out", "err"],
+    };
+
+    log_args
+        .projects
+        .iter()
+        .enumerate()
+        .flat_map(|(i, project)| {
+            let color = LABEL_COLORS[i % LABEL_COLORS.len()];
+            streams.iter().map(move |stream| LogSource {
+                label: format!("{}.{}", project.name, stream),
+                color,
+                path: LOG_DIR.join(format!("{}.{}", project.name, stream)),
+            })
+        })
+        .collect()
+}
+
+fn format_line(source: &LogSource, line: &str) -> String {
+    format!("\x1b[{}m{}\x1b[0m | {}", source.color, source.label, line)
+}
+
+fn print_tail(source: &LogSource, lines: usize) -> Result<(), anyhow::Error> {
+    let mut file = File::open(&source.path)?;
+    let offset = tail_offset(&mut file, lines)?;
+    file.seek(SeekFrom::Start(offset))?;
+
+    for line in BufReader::new(file).lines() {
+        println!("{}", format_line(source, &line?));
+    }
+
+    Ok(())
+}
+
+// A `LogSource` plus the reader state needed to follow it and detect
+// rotation.
+struct FollowSource {
+    source: LogSource,
+    reader: BufReader<File>,
+    last_len: u64,
+    last_ino: u64,
+}
+
+// Polls every source in turn, draining what's available on each before
+// moving to the next, so one idle stream doesn't block another.
+fn follow_logs(sources: Vec<LogSource>, lines: usize) -> Result<(), anyhow::Error> {
+    let mut followed = Vec::new();
+    for source in sources {
+        let mut file = File::open(&source.path)?;
+        let offset = tail_offset(&mut file, lines)?;
+        file.seek(SeekFrom::Start(offset))?;
+
+        let meta = file.metadata()?;
+        followed.push(FollowSource {
+            last_len: offset,
+            last_ino: meta.ino(),
+            reader: BufReader::new(file),
+            source,
+        });
+    }
 
-    let mut reader = BufReader::new(file);
     let mut buffer = String::new();
+    loop {
+        let mut any_data = false;
 
-    if log_args.follow {
-        loop {
-            match reader.read_line(&mut buffer) {
-                Ok(0) => {
-                    // No new data, so wait before trying again
-                    sleep(Duration::from_secs(1));
-                }
-                Ok(_) => {
-                    print!("{}", buffer);
-                    buffer.clear(); // Clear the buffer after printing
-                }
-                Err(e) => {
-                    eprintln!("Error reading from file: {}", e);
-                    bail!(e)
+        for f in &mut followed {
+            loop {
+                buffer.clear();
+                match f.reader.read_line(&mut buffer) {
+                    Ok(0) => break,
+                    Ok(n) => {
+                        any_data = true;
+                        f.last_len += n as u64;
+                        println!("{}", format_line(&f.source, buffer.trim_end_matches('\n')));
+                    }
+                    Err(e) => {
+                        eprintln!("Error reading from file: {}", e);
+                        bail!(e)
+                    }
                 }
             }
+
+            // No new data on this pass. Check whether the log was
+            // rotated/truncated (e.g. a `stop`/`start` recreating it in
+            // LOG_DIR) so the follower doesn't silently wedge on a file
+            // that no longer exists at this offset.
+            let meta = std::fs::metadata(&f.source.path)?;
+            if meta.len() < f.last_len || meta.ino() != f.last_ino {
+                let new_file = File::open(&f.source.path)?;
+                f.last_len = 0;
+                f.last_ino = new_file.metadata()?.ino();
+                f.reader = BufReader::new(new_file);
+            }
         }
+
+        if !any_data {
+            sleep(Duration::from_millis(200));
+        }
+    }
+}
+
+fn log(log_args: LogsArgs) -> Result<(), anyhow::Error> {
+    let sources = log_sources(&log_args);
+
+    if log_args.follow {
+        follow_logs(sources, log_args.lines)
     } else {
-        reader.read_to_string(&mut buffer)?;
-        println!("{}", buffer);
+        for source in &sources {
+            print_tail(source, log_args.lines)?;
+        }
+        Ok(())
     }
+}
 
-    Ok(())
+// `.pid1` sidecar files (see [`fork_into_pid_namespace`]) share STATE_DIR
+// with the `{name}-{pid}` state files but aren't one of them.
+fn is_state_file(path: &Path) -> bool {
+    path.extension().is_none_or(|ext| ext != "pid1")
 }
 
 fn parse_state_filename(path: &Path) -> anyhow::Result<(String, i32)> {
@@ -82,41 +241,98 @@ fn status() -> Result<(), anyhow::Error> {
 
     for entry in std::fs::read_dir(STATE_DIR.as_path())? {
         let path = entry?.path();
+        if !is_state_file(&path) {
+            continue;
+        }
 
         let f = File::open(&path)?;
         let reader = BufReader::new(f);
-        let project: Project = serde_json::from_reader(reader)?;
+        let state: StateFile = serde_json::from_reader(reader)?;
 
         let (_, pid) = parse_state_filename(&path)?;
 
         if is_process_running(pid) {
-            set.insert(project.display.unwrap_or(project.name));
+            let display = state.project.display.unwrap_or(state.project.name);
+            let line = if state.state.failed {
+                format!("{} is failed", display)
+            } else if state.state.restarts > 0 {
+                format!("{} is running ({} restarts)", display, state.state.restarts)
+            } else {
+                format!("{} is running", display)
+            };
+            set.insert(line);
         } else {
             // If the process isn't running, then there is no need to keep the file
             std::fs::remove_file(path)?;
         }
     }
 
-    for project in set {
-        println!("{} is running", project);
+    for line in set {
+        println!("{}", line);
     }
 
     Ok(())
 }
 
-fn stop(projects: Vec<Project>) -> Result<(), anyhow::Error> {
-    // Try to terminate all processes that the user wants to stop
+// Resolves `project.stop.signal`/`--signal`, falling back to SIGTERM.
+// Rejects SIGKILL/SIGSTOP: uncatchable, they'd hit the supervisor instead
+// of being relayed to the project's process.
+fn stop_signal(project: &Project, signal_override: Option<::libc::c_int>) -> anyhow::Result<::libc::c_int> {
+    let signal = match signal_override {
+        Some(signal) => signal,
+        None => match project.stop.as_ref().and_then(|s| s.signal.as_deref()) {
+            Some(name) => signal_from_name(name)?,
+            None => libc::SIGTERM,
+        },
+    };
+
+    if !is_catchable_signal(signal) {
+        bail!("Signal {} can't be caught by the supervisor and would kill it directly instead of the project it manages", signal);
+    }
+
+    Ok(signal)
+}
+
+fn stop_timeout(project: &Project) -> Duration {
+    Duration::from_millis(project.stop.as_ref().and_then(|s| s.timeout_ms).unwrap_or(5_000))
+}
+
+// Defaults to `stop_timeout` if `kill_after` isn't configured.
+fn stop_kill_after(project: &Project) -> Duration {
+    project
+        .stop
+        .as_ref()
+        .and_then(|s| s.kill_after_ms)
+        .map(Duration::from_millis)
+        .unwrap_or_else(|| stop_timeout(project))
+}
+
+// Signals the supervisors of `projects` and waits for them to exit; the
+// actual signal/wait/SIGKILL escalation against each project's process
+// happens one level down, in [`shutdown_child`].
+fn stop(projects: Vec<Project>, signal_override: Option<::libc::c_int>) -> Result<(), anyhow::Error> {
+    // Send each project's configured (or overridden) signal to the
+    // supervisors of the projects the user wants to stop
     for entry in std::fs::read_dir(STATE_DIR.as_path())? {
         let path = entry?.path();
+        if !is_state_file(&path) {
+            continue;
+        }
 
-        let (project, pid) = parse_state_filename(&path)?;
+        let (name, pid) = parse_state_filename(&path)?;
 
-        if projects.iter().any(|p| p.name == project) {
-            let _ = terminate(pid);
+        if let Some(p) = projects.iter().find(|p| p.name == name) {
+            let _ = send_signal(pid, stop_signal(p, signal_override)?);
         };
     }
 
-    let timeout = Duration::new(5, 0);
+    // Give each supervisor long enough to run its own signal/wait/SIGKILL
+    // escalation against its child before giving up on it here.
+    let timeout = projects
+        .iter()
+        .map(|p| stop_timeout(p) + stop_kill_after(p))
+        .max()
+        .unwrap_or(Duration::from_secs(5));
     let start = Instant::now();
 
     let mut set: HashSet<String> = HashSet::new();
@@ -126,6 +342,9 @@ fn stop(projects: Vec<Project>) -> Result<(), anyhow::Error> {
         set.clear();
         for entry in std::fs::read_dir(STATE_DIR.as_path())? {
             let path = entry?.path();
+            if !is_state_file(&path) {
+                continue;
+            }
 
             let (project, pid) = parse_state_filename(&path)?;
 
@@ -136,8 +355,9 @@ fn stop(projects: Vec<Project>) -> Result<(), anyhow::Error> {
                 } else {
                     std::fs::remove_file(path)?;
 
-                    let log_file = LOG_DIR.join(&p.name);
-                    let _ = std::fs::remove_file(log_file);
+                    let _ = std::fs::remove_file(LOG_DIR.join(format!("{}.out", p.name)));
+                    let _ = std::fs::remove_file(LOG_DIR.join(format!("{}.err", p.name)));
+                    let _ = std::fs::remove_file(STATE_DIR.join(format!("{}.pid1", p.name)));
                 }
             };
         }
@@ -156,36 +376,21 @@ fn stop(projects: Vec<Project>) -> Result<(), anyhow::Error> {
     Ok(())
 }
 
-fn start(projects: Vec<Project>) -> Result<(), anyhow::Error> {
+// `force_watch` distinguishes `start` (only watches configured projects)
+// from `watch` (watches every requested project, defaulting to `cwd`).
+fn start(projects: Vec<Project>, force_watch: bool) -> Result<(), anyhow::Error> {
+    let projects = resolve_start_order(projects)?;
+
     let master_pid = sysinfo::get_current_pid().unwrap();
     for project in projects {
         match daemon().map_err(|e| anyhow!("Error: {} on daemon: {:?}", e, project))? {
             Fork::Parent(pid) => {
-                let filename = format!("{}-{}", project.name, pid);
-                let state_file = STATE_DIR.join(filename);
-
-                let file = File::create(state_file)?;
-                serde_json::to_writer(file, &project)?;
-            }
-            Fork::Child => {
-                let tmp_file = LOG_DIR.join(&project.name);
-                let f = File::create(tmp_file)?;
-
-                // Create a raw filedescriptor to use to merge stdout and stderr
-                let fd = f.into_raw_fd();
-
-                let parts = shlex::split(&project.command)
-                    .context(format!("Couldn't parse command: {}", project.command))?;
-
-                std::process::Command::new(&parts[0])
-                    .args(&parts[1..])
-                    .envs(project.envs.unwrap_or_default())
-                    .current_dir(project.cwd)
-                    .stdout(unsafe { Stdio::from_raw_fd(fd) })
-                    .stderr(unsafe { Stdio::from_raw_fd(fd) })
-                    .stdin(Stdio::null())
-                    .exec();
+                write_state(&project, pid, &State { restarts: 0, failed: false })?;
+                // "Started" means "ready", not merely "spawned": block here until the
+                // project's `ready` probe passes so dependents aren't started early.
+                wait_until_ready(&project)?;
             }
+            Fork::Child => supervise(project, force_watch)?,
         }
 
         // Prevent trying to start a project multiple times
@@ -198,6 +403,422 @@ fn start(projects: Vec<Project>) -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+// The `Watch` a project should actually be monitored with: its configured
+// one, or (only if `force_watch` asked for it) a default watching `cwd`.
+fn effective_watch(project: &Project, force_watch: bool) -> Option<Watch> {
+    project.watch.clone().or_else(|| {
+        force_watch.then(|| Watch { paths: vec![project.cwd.clone()], ignore: None, debounce_ms: None })
+    })
+}
+
+// Expands `projects` to the full transitive closure of their `depends_on`,
+// looked up from `all`, so e.g. `worker start api` also brings up `db`.
+fn dependency_closure(projects: Vec<Project>, all: &[Project]) -> Result<Vec<Project>, anyhow::Error> {
+    let all_by_name: HashMap<String, Project> = all.iter().map(|p| (p.name.clone(), p.clone())).collect();
+
+    let mut closure = Vec::new();
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut queue: VecDeque<Project> = projects.into_iter().collect();
+
+    while let Some(project) = queue.pop_front() {
+        if !seen.insert(project.name.clone()) {
+            continue;
+        }
+
+        for dep in project.depends_on.clone().unwrap_or_default() {
+            if seen.contains(&dep) {
+                continue;
+            }
+            let dep_project = all_by_name.get(&dep).with_context(|| {
+                format!("Project `{}` depends on `{}`, which isn't declared in `{}`", project.name, dep, CONFIG_FILE)
+            })?;
+            queue.push_back(dep_project.clone());
+        }
+
+        closure.push(project);
+    }
+
+    Ok(closure)
+}
+
+fn resolve_start_order(projects: Vec<Project>) -> Result<Vec<Project>, anyhow::Error> {
+    let closure = dependency_closure(projects, &all_projects()?)?;
+    order_by_dependencies(closure)
+}
+
+// Orders `closure` via Kahn's algorithm so every project comes after its
+// `depends_on`. Bails with the offending chain if a cycle is found.
+fn order_by_dependencies(closure: Vec<Project>) -> Result<Vec<Project>, anyhow::Error> {
+    let by_name: HashMap<String, Project> = closure.iter().map(|p| (p.name.clone(), p.clone())).collect();
+
+    let mut in_degree: HashMap<String, usize> = closure.iter().map(|p| (p.name.clone(), 0)).collect();
+    let mut dependents: HashMap<String, Vec<String>> = HashMap::new();
+
+    for project in &closure {
+        for dep in project.depends_on.clone().unwrap_or_default() {
+            *in_degree.get_mut(&project.name).unwrap() += 1;
+            dependents.entry(dep).or_default().push(project.name.clone());
+        }
+    }
+
+    let mut queue: VecDeque<String> =
+        closure.iter().map(|p| p.name.clone()).filter(|name| in_degree[name] == 0).collect();
+
+    let mut order = Vec::new();
+    while let Some(name) = queue.pop_front() {
+        order.push(name.clone());
+
+        for dependent in dependents.get(&name).into_iter().flatten() {
+            let degree = in_degree.get_mut(dependent).unwrap();
+            *degree -= 1;
+            if *degree == 0 {
+                queue.push_back(dependent.clone());
+            }
+        }
+    }
+
+    if order.len() != closure.len() {
+        // Every project left unordered still waits on an unordered dependency,
+        // so following those from any of them loops back onto the cycle.
+        let mut chain: Vec<String> = Vec::new();
+        let mut next = closure.iter().map(|p| p.name.clone()).find(|name| !order.contains(name));
+        while let Some(name) = next {
+            if let Some(start) = chain.iter().position(|n| *n == name) {
+                chain.drain(..start);
+                chain.push(name);
+                break;
+            }
+            next = by_name.get(&name).and_then(|p| p.depends_on.iter().flatten().find(|dep| !order.contains(dep)).cloned());
+            chain.push(name);
+        }
+        bail!("Cycle detected in `depends_on`: {}", chain.join(" -> "));
+    }
+
+    Ok(order.into_iter().map(|name| by_name[&name].clone()).collect())
+}
+
+// Blocks until `project.ready` passes (a TCP connection to `tcp` succeeds,
+// or `command` exits 0), or bails once `timeout_ms` has elapsed. Does
+// nothing if the project has no `ready` probe configured.
+fn wait_until_ready(project: &Project) -> Result<(), anyhow::Error> {
+    let Some(ready) = &project.ready else {
+        return Ok(());
+    };
+
+    let timeout = Duration::from_millis(ready.timeout_ms.unwrap_or(30_000));
+    let start = Instant::now();
+
+    loop {
+        if probe_ready(ready)? {
+            return Ok(());
+        }
+
+        if start.elapsed() >= timeout {
+            bail!("Project `{}` did not become ready within {:?}", project.name, timeout);
+        }
+
+        sleep(Duration::from_millis(200));
+    }
+}
+
+fn probe_ready(ready: &Ready) -> Result<bool, anyhow::Error> {
+    if let Some(addr) = &ready.tcp {
+        return Ok(std::net::TcpStream::connect(addr).is_ok());
+    }
+
+    if let Some(command) = &ready.command {
+        let parts = shlex::split(command).context(format!("Couldn't parse ready command: {}", command))?;
+        let status = std::process::Command::new(&parts[0]).args(&parts[1..]).status();
+        return Ok(status.map(|s| s.success()).unwrap_or(false));
+    }
+
+    Ok(true)
+}
+
+const INITIAL_BACKOFF: Duration = Duration::from_millis(200);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+// A crash this long after the previous (re)start is treated as unrelated to
+// it, so the backoff resets instead of keeping climbing towards the cap.
+const BACKOFF_RESET_AFTER: Duration = Duration::from_secs(60);
+
+fn write_state(project: &Project, pid: libc::Pid, state: &State) -> Result<(), anyhow::Error> {
+    let filename = format!("{}-{}", project.name, pid);
+    let state_file = STATE_DIR.join(filename);
+
+    let file = File::create(state_file)?;
+    serde_json::to_writer(file, &StateFile { project: project.clone(), state: state.clone() })?;
+    Ok(())
+}
+
+// Forks a grandchild that `exec`s the project's command, redirecting
+// stdout/stderr to `name.out`/`name.err` in LOG_DIR. Returns its pid.
+fn spawn_child(project: &Project) -> Result<libc::Pid, anyhow::Error> {
+    match fork().map_err(|e| anyhow!("Error: {} on fork for {}", e, project.name))? {
+        Fork::Parent(pid) => Ok(pid),
+        Fork::Child => {
+            let stdout_fd = File::create(LOG_DIR.join(format!("{}.out", project.name)))?.into_raw_fd();
+            let stderr_fd = File::create(LOG_DIR.join(format!("{}.err", project.name)))?.into_raw_fd();
+
+            let parts = shlex::split(&project.command)
+                .context(format!("Couldn't parse command: {}", project.command))?;
+
+            if let Some(sandbox) = &project.sandbox {
+                apply_sandbox(sandbox, &project.name).context(format!("Couldn't sandbox `{}`", project.name))?;
+            }
+
+            let err = std::process::Command::new(&parts[0])
+                .args(&parts[1..])
+                .envs(project.envs.clone().unwrap_or_default())
+                .current_dir(&project.cwd)
+                .stdout(unsafe { Stdio::from_raw_fd(stdout_fd) })
+                .stderr(unsafe { Stdio::from_raw_fd(stderr_fd) })
+                .stdin(Stdio::null())
+                .exec();
+
+            // `exec` only returns on failure
+            eprintln!("Failed to exec `{}`: {}", project.command, err);
+            std::process::exit(127);
+        }
+    }
+}
+
+// Applies `sandbox`'s rlimits, namespace isolation and capability drops to
+// the forked child, before it `exec`s. Namespace isolation runs first since
+// the `pid` toggle forks again (see [`fork_into_pid_namespace`]) and the
+// rest must land on the grandchild that leaves behind, not the caller.
+fn apply_sandbox(sandbox: &Sandbox, project_name: &str) -> Result<(), anyhow::Error> {
+    if let Some(namespaces) = sandbox.namespaces {
+        if namespaces.pid.unwrap_or(false) {
+            let pid_file = STATE_DIR.join(format!("{}.pid1", project_name));
+            fork_into_pid_namespace(&pid_file).context("Couldn't isolate into a new PID namespace")?;
+        }
+
+        unshare_namespaces(NamespaceToggles {
+            net: namespaces.net.unwrap_or(false),
+            mount: namespaces.mount.unwrap_or(false),
+            uts: namespaces.uts.unwrap_or(false),
+        })
+        .context("Couldn't apply requested namespace isolation")?;
+    }
+
+    for (name, limit) in sandbox.rlimits.iter().flatten() {
+        let resource = rlimit_resource_from_name(name).context(format!("Invalid rlimit `{}`", name))?;
+        set_rlimit(resource, limit.soft, limit.hard).context(format!("Couldn't set rlimit `{}`", name))?;
+    }
+
+    for name in sandbox.drop_capabilities.iter().flatten() {
+        let cap = capability_from_name(name).context(format!("Invalid capability `{}`", name))?;
+        drop_capability(cap).context(format!("Couldn't drop capability `{}`", name))?;
+    }
+
+    Ok(())
+}
+
+// Returns true if any of the changed paths in `event` match one of the
+// configured `ignore` globs, meaning the event should not count towards
+// a restart.
+fn is_ignored(event: &notify::Event, ignore: &[String]) -> bool {
+    event.paths.iter().any(|path| {
+        ignore.iter().any(|pattern| {
+            glob::Pattern::new(pattern)
+                .map(|g| g.matches_path(path))
+                .unwrap_or(false)
+        })
+    })
+}
+
+// Spawns a background thread that watches `watch.paths` and flips the
+// returned flag once events have been quiet for `debounce_ms`.
+fn spawn_watch_thread(name: String, watch: Watch) -> Result<Arc<AtomicBool>, anyhow::Error> {
+    let debounce = Duration::from_millis(watch.debounce_ms.unwrap_or(250));
+    let ignore = watch.ignore.unwrap_or_default();
+    let flag = Arc::new(AtomicBool::new(false));
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        if let Ok(event) = res {
+            let _ = tx.send(event);
+        }
+    })?;
+
+    for path in &watch.paths {
+        watcher
+            .watch(Path::new(path), RecursiveMode::Recursive)
+            .context(format!("Couldn't watch path: {}", path))?;
+    }
+
+    let thread_flag = flag.clone();
+    std::thread::spawn(move || {
+        // Keep the watcher alive for the lifetime of the thread
+        let _watcher = watcher;
+        let mut last_event: Option<Instant> = None;
+
+        loop {
+            match rx.recv_timeout(Duration::from_millis(50)) {
+                Ok(event) => {
+                    if !is_ignored(&event, &ignore) {
+                        last_event = Some(Instant::now());
+                    }
+                }
+                Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {}
+                Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => break,
+            }
+
+            if let Some(at) = last_event {
+                if at.elapsed() >= debounce {
+                    println!("Change detected for `{}`, restarting", name);
+                    thread_flag.store(true, Ordering::SeqCst);
+                    last_event = None;
+                }
+            }
+        }
+    });
+
+    Ok(flag)
+}
+
+// Sends the project's stop signal (default SIGTERM) to its child and
+// escalates to SIGKILL past `kill_after`. Blocks until the child exits.
+fn shutdown_child(child_pid: libc::Pid, project: &Project) -> Result<(), anyhow::Error> {
+    let _ = send_signal(child_pid, stop_signal(project, None)?);
+
+    let kill_after = stop_kill_after(project);
+    let start = Instant::now();
+    let mut killed = false;
+
+    loop {
+        // `try_waitpid` (unlike `is_process_running`) reaps the child once it
+        // exits, so this doesn't spin forever on an unreaped zombie.
+        if try_waitpid(child_pid)?.is_some() {
+            return Ok(());
+        }
+
+        if !killed && start.elapsed() >= kill_after {
+            let _ = send_signal(force_kill_target(child_pid, project), libc::SIGKILL);
+            killed = true;
+        }
+
+        sleep(Duration::from_millis(50));
+    }
+}
+
+// `child_pid` is the relay `fork_into_pid_namespace` leaves behind, not the
+// real process - SIGKILL can't be relayed, so this targets the grandchild's
+// real pid (from its `.pid1` file) directly, falling back to `child_pid`.
+fn force_kill_target(child_pid: libc::Pid, project: &Project) -> libc::Pid {
+    let uses_pid_namespace =
+        project.sandbox.as_ref().and_then(|s| s.namespaces).and_then(|n| n.pid).unwrap_or(false);
+
+    if !uses_pid_namespace {
+        return child_pid;
+    }
+
+    let pid_file = STATE_DIR.join(format!("{}.pid1", project.name));
+    std::fs::read_to_string(pid_file).ok().and_then(|s| s.trim().parse().ok()).unwrap_or(child_pid)
+}
+
+// Sleeps for `duration` in short slices, polling `shutdown_requested` so a
+// `stop` sent mid-backoff is noticed early. Returns true if it was.
+fn sleep_checking_shutdown(duration: Duration) -> bool {
+    const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+    let start = Instant::now();
+    while start.elapsed() < duration {
+        if shutdown_requested() {
+            return true;
+        }
+        sleep(POLL_INTERVAL.min(duration - start.elapsed()));
+    }
+
+    shutdown_requested()
+}
+
+// Runs for the lifetime of the project: forks/execs the command, and on
+// crash respawns with capped exponential backoff up to `max_restarts`. A
+// watched file change (see [`effective_watch`]) also respawns, without
+// counting against `max_restarts` or the backoff.
+fn supervise(project: Project, force_watch: bool) -> Result<(), anyhow::Error> {
+    install_shutdown_handler();
+    let pid = std::process::id() as libc::Pid;
+
+    let watch_restart = effective_watch(&project, force_watch)
+        .map(|watch| spawn_watch_thread(project.name.clone(), watch))
+        .transpose()?;
+
+    let mut restarts: u32 = 0;
+    let mut backoff = INITIAL_BACKOFF;
+
+    loop {
+        let started_at = Instant::now();
+        let child_pid = spawn_child(&project)?;
+
+        let crashed = loop {
+            if shutdown_requested() {
+                shutdown_child(child_pid, &project)?;
+                return Ok(());
+            }
+
+            if let Some(flag) = &watch_restart {
+                if flag.swap(false, Ordering::SeqCst) {
+                    let _ = send_signal(child_pid, libc::SIGTERM);
+                    let _ = waitpid(child_pid);
+                    break false;
+                }
+            }
+
+            if let Some(status) = try_waitpid(child_pid)? {
+                if status != 0 {
+                    break true;
+                }
+
+                // A clean exit isn't a crash: nothing to respawn until a watched change
+                let Some(flag) = &watch_restart else {
+                    return Ok(());
+                };
+                while !flag.swap(false, Ordering::SeqCst) {
+                    if shutdown_requested() {
+                        return Ok(());
+                    }
+                    sleep(Duration::from_millis(100));
+                }
+                break false;
+            }
+
+            sleep(Duration::from_millis(100));
+        };
+
+        if !crashed {
+            // A deliberate restart due to a file change isn't a crash
+            continue;
+        }
+
+        if started_at.elapsed() >= BACKOFF_RESET_AFTER {
+            backoff = INITIAL_BACKOFF;
+        }
+
+        if let Some(max) = project.max_restarts {
+            if restarts >= max {
+                write_state(&project, pid, &State { restarts, failed: true })?;
+                // Park so the state file (and its `failed` marker) sticks around
+                // until the user explicitly stops the project.
+                loop {
+                    if shutdown_requested() {
+                        return Ok(());
+                    }
+                    sleep(Duration::from_secs(1));
+                }
+            }
+        }
+
+        restarts += 1;
+        write_state(&project, pid, &State { restarts, failed: false })?;
+        if sleep_checking_shutdown(backoff) {
+            return Ok(());
+        }
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
 #[derive(Deserialize, Debug)]
 struct Config {
     project: Vec<Project>,
@@ -210,30 +831,96 @@ struct Project {
     cwd: String,
     display: Option<String>,
     envs: Option<HashMap<String, String>>,
+    watch: Option<Watch>,
+    max_restarts: Option<u32>,
+    depends_on: Option<Vec<String>>,
+    ready: Option<Ready>,
+    sandbox: Option<Sandbox>,
+    stop: Option<Stop>,
+}
+
+// Escalation policy for stopping this project: which signal to send first,
+// how long to wait for it to exit, and when to give up waiting and send
+// SIGKILL.
+#[derive(Deserialize, Clone, Debug, Serialize)]
+struct Stop {
+    signal: Option<String>,
+    timeout_ms: Option<u64>,
+    kill_after_ms: Option<u64>,
+}
+
+// Resource and isolation controls applied to the project's command right
+// before it `exec`s.
+#[derive(Deserialize, Clone, Debug, Serialize)]
+struct Sandbox {
+    rlimits: Option<HashMap<String, RlimitValue>>,
+    namespaces: Option<SandboxNamespaces>,
+    drop_capabilities: Option<Vec<String>>,
+}
+
+#[derive(Deserialize, Clone, Debug, Serialize)]
+struct RlimitValue {
+    soft: u64,
+    hard: u64,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, Serialize, Default)]
+struct SandboxNamespaces {
+    pid: Option<bool>,
+    net: Option<bool>,
+    mount: Option<bool>,
+    uts: Option<bool>,
+}
+
+// A readiness probe: either a `host:port` that must accept a TCP
+// connection, or a shell command that must exit 0.
+#[derive(Deserialize, Clone, Debug, Serialize)]
+struct Ready {
+    tcp: Option<String>,
+    command: Option<String>,
+    timeout_ms: Option<u64>,
+}
+
+// Runtime supervision status, recorded in STATE_DIR alongside the `Project`
+// so `status()` can report it without needing to talk to the supervisor.
+#[derive(Deserialize, Clone, Debug, Serialize)]
+struct State {
+    restarts: u32,
+    failed: bool,
+}
+
+#[derive(Deserialize, Clone, Debug, Serialize)]
+struct StateFile {
+    project: Project,
+    state: State,
+}
+
+#[derive(Deserialize, Clone, Debug, Serialize)]
+struct Watch {
+    paths: Vec<String>,
+    ignore: Option<Vec<String>>,
+    debounce_ms: Option<u64>,
+}
+
+// Every project declared in `.worker.toml`, regardless of what was passed
+// on the CLI.
+fn all_projects() -> Result<Vec<Project>, anyhow::Error> {
+    let config_file = CONFIG_DIR.join(CONFIG_FILE);
+    let config_string = std::fs::read_to_string(config_file)?;
+
+    // Deserialize the TOML string into the Config struct
+    let config: Config = toml::from_str(&config_string)?;
+    Ok(config.project)
 }
 
 impl FromStr for Project {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let config_file = CONFIG_DIR.join(CONFIG_FILE);
-        let config_string = std::fs::read_to_string(config_file)?;
-
-        // Deserialize the TOML string into the Config struct
-        let config: Config = toml::from_str(&config_string)?;
+        let projects = all_projects()?;
+        let names = projects.iter().map(|p| p.name.clone()).collect::<Vec<String>>();
 
-        let projects = config
-            .project
-            .iter()
-            .map(|p| p.name.clone())
-            .collect::<Vec<String>>();
-
-        config
-            .project
-            .clone()
-            .into_iter()
-            .find(|it| it.name == s)
-            .context(format!("Valid projects are {:?}", projects))
+        projects.into_iter().find(|it| it.name == s).context(format!("Valid projects are {:?}", names))
     }
 }
 
@@ -242,24 +929,55 @@ struct ActionArgs {
     projects: Vec<Project>,
 }
 
+#[derive(Debug, Parser)]
+struct StopArgs {
+    projects: Vec<Project>,
+    /// Override each project's configured stop signal. Accepts a name
+    /// (`SIGINT`, `INT`) or a raw number
+    #[arg(long)]
+    signal: Option<String>,
+}
+
 #[derive(Debug, Parser)]
 struct LogsArgs {
-    project: Project,
+    projects: Vec<Project>,
     #[arg(short, long)]
     follow: bool,
+    /// Only print the last N lines before printing/following. Defaults to 10
+    #[arg(short = 'n', long, default_value_t = 10)]
+    lines: usize,
+    /// Which of stdout/stderr to print. Defaults to both
+    #[arg(long, value_enum, default_value_t = Stream::Both)]
+    stream: Stream,
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+enum Stream {
+    Out,
+    Err,
+    Both,
 }
 
 #[derive(Parser, Debug)]
 enum SubCommands {
-    /// Starts the specified project(s). E.g. `worker start foo bar`
+    /// Starts the specified project(s). E.g. `worker start foo bar`. Only
+    /// watches for file changes if the project configures a `watch` section.
     Start(ActionArgs),
-    /// Stops the specified project(s). E.g. `worker stop foo bar`
-    Stop(ActionArgs),
+    /// Stops the specified project(s). E.g. `worker stop foo bar`.
+    /// `--signal` overrides the configured stop signal, e.g. `worker stop --signal SIGINT foo`
+    Stop(StopArgs),
     /// Restarts the specified project(s). E.g. `worker restart foo bar` (Same as running stop and then start)
     Restart(ActionArgs),
-    /// Print out logs for the specified project.
-    /// Additionally accepts `-f` to follow the log. E.g. `worker logs foo`
-    Logs(LogsArgs),
+    /// Starts the specified project(s) and restarts them whenever files
+    /// change, unlike `start`: their configured `watch` paths if they have
+    /// one, otherwise their `cwd`. E.g. `worker watch foo`
+    Watch(ActionArgs),
+    /// Print out logs for the specified project(s), each line prefixed with
+    /// its project and stream. Without `-f` each file's tail is printed in
+    /// turn; with `-f` all of them are followed, interleaved. `--stream`
+    /// picks stdout/stderr/both.
+    /// E.g. `worker logs foo bar`
+    Logs(Box<LogsArgs>),
     /// Prints out a status of which projects is running. Accepts no additional flags or project(s)
     Status,
 }
@@ -295,15 +1013,133 @@ fn main() -> Result<(), anyhow::Error> {
     std::fs::create_dir_all(LOG_DIR.as_path())?;
 
     match args.subcommand {
-        SubCommands::Start(args) => start(args.projects)?,
-        SubCommands::Stop(args) => stop(args.projects)?,
+        SubCommands::Start(args) => start(args.projects, false)?,
+        SubCommands::Stop(args) => {
+            let signal = args.signal.as_deref().map(signal_from_name).transpose()?;
+            stop(args.projects, signal)?
+        }
         SubCommands::Restart(args) => {
-            stop(args.projects.clone())?;
-            start(args.projects)?;
+            stop(args.projects.clone(), None)?;
+            start(args.projects, false)?;
         }
-        SubCommands::Logs(log_args) => log(log_args)?,
+        SubCommands::Watch(args) => start(args.projects, true)?,
+        SubCommands::Logs(log_args) => log(*log_args)?,
         SubCommands::Status => status()?,
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use super::*;
+
+    fn project(name: &str, depends_on: &[&str]) -> Project {
+        Project {
+            name: name.to_string(),
+            command: "true".to_string(),
+            cwd: ".".to_string(),
+            display: None,
+            envs: None,
+            watch: None,
+            max_restarts: None,
+            depends_on: (!depends_on.is_empty())
+                .then(|| depends_on.iter().map(|s| s.to_string()).collect()),
+            ready: None,
+            sandbox: None,
+            stop: None,
+        }
+    }
+
+    fn names(projects: &[Project]) -> Vec<&str> {
+        projects.iter().map(|p| p.name.as_str()).collect()
+    }
+
+    fn write_tail_fixture(contents: &[u8]) -> File {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let mut path = std::env::temp_dir();
+        path.push(format!(
+            "worker-tail-test-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::SeqCst)
+        ));
+        std::fs::write(&path, contents).unwrap();
+        File::open(&path).unwrap()
+    }
+
+    #[test]
+    fn tail_offset_empty_file() {
+        let mut f = write_tail_fixture(b"");
+        assert_eq!(tail_offset(&mut f, 5).unwrap(), 0);
+    }
+
+    #[test]
+    fn tail_offset_zero_lines_returns_eof() {
+        let mut f = write_tail_fixture(b"a\nb\nc\n");
+        assert_eq!(tail_offset(&mut f, 0).unwrap(), 6);
+    }
+
+    #[test]
+    fn tail_offset_fewer_lines_than_requested_returns_bof() {
+        let mut f = write_tail_fixture(b"a\nb\n");
+        assert_eq!(tail_offset(&mut f, 10).unwrap(), 0);
+    }
+
+    #[test]
+    fn tail_offset_trailing_newline_not_counted_as_its_own_line() {
+        // Last line is "c", terminated by a trailing newline - asking for 1
+        // line should start at "c", not at the empty line after it.
+        let mut f = write_tail_fixture(b"a\nb\nc\n");
+        let offset = tail_offset(&mut f, 1).unwrap();
+        let mut rest = String::new();
+        f.seek(SeekFrom::Start(offset)).unwrap();
+        f.read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "c\n");
+    }
+
+    #[test]
+    fn tail_offset_without_trailing_newline() {
+        let mut f = write_tail_fixture(b"a\nb\nc");
+        let offset = tail_offset(&mut f, 1).unwrap();
+        let mut rest = String::new();
+        f.seek(SeekFrom::Start(offset)).unwrap();
+        f.read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "c");
+    }
+
+    #[test]
+    fn dependency_closure_pulls_in_undeclared_transitive_deps() {
+        let all = vec![project("api", &["db"]), project("db", &[]), project("worker", &[])];
+        let closure = dependency_closure(vec![all[0].clone()], &all).unwrap();
+        assert_eq!(names(&closure), vec!["api", "db"]);
+    }
+
+    #[test]
+    fn dependency_closure_missing_dependency_errors() {
+        let all = vec![project("api", &["db"])];
+        assert!(dependency_closure(vec![all[0].clone()], &all).is_err());
+    }
+
+    #[test]
+    fn order_by_dependencies_puts_dependency_first() {
+        let closure = vec![project("api", &["db"]), project("db", &[])];
+        let order = order_by_dependencies(closure).unwrap();
+        assert_eq!(names(&order), vec!["db", "api"]);
+    }
+
+    #[test]
+    fn order_by_dependencies_keeps_cli_order_with_no_deps() {
+        let closure = vec![project("b", &[]), project("a", &[]), project("c", &[])];
+        let order = order_by_dependencies(closure).unwrap();
+        assert_eq!(names(&order), vec!["b", "a", "c"]);
+    }
+
+    #[test]
+    fn order_by_dependencies_detects_cycle() {
+        let closure = vec![project("a", &["b"]), project("b", &["a"])];
+        assert!(order_by_dependencies(closure).is_err());
+    }
+}